@@ -1,3 +1,4 @@
+use std::cell::Cell;
 use std::error;
 use std::fmt;
 use std::io;
@@ -6,6 +7,26 @@ use std::string::FromUtf8Error;
 
 use serde::de;
 
+thread_local! {
+    // `unknown_field`/`missing_field` take no position argument, since
+    // they come from a `de::Error` trait written for old-style
+    // deserializers that never tracked one. `de::Deserializer::parse_whitespace`
+    // records where it last stopped here, at every token boundary, so
+    // these two error paths can still report a real line/column/byte
+    // offset instead of hardcoding `0, 0, 0`.
+    static LAST_POSITION: Cell<(usize, usize, usize)> = Cell::new((0, 0, 0));
+}
+
+/// Record the position `de::Deserializer::parse_whitespace` last stopped
+/// at, so the next `unknown_field`/`missing_field` error can report it.
+pub(crate) fn set_position(line: usize, column: usize, byte_offset: usize) {
+    LAST_POSITION.with(|cell| cell.set((line, column, byte_offset)));
+}
+
+fn last_position() -> (usize, usize, usize) {
+    LAST_POSITION.with(|cell| cell.get())
+}
+
 /// The errors that can arise while parsing a JSON stream.
 #[derive(Clone, PartialEq)]
 pub enum ErrorCode {
@@ -38,6 +59,29 @@ pub enum ErrorCode {
     UnexpectedEndOfHexEscape,
     UnknownVariant,
     UnrecognizedHex,
+    InvalidBase64,
+    /// A caller-supplied message from a `Deserialize`/`Serialize` impl that
+    /// doesn't correspond to one of the codes above, such as a `Visitor`
+    /// rejecting a value that is syntactically fine JSON but wrong for the
+    /// target type.
+    Message(String),
+}
+
+impl ErrorCode {
+    /// Which broad category this error code falls under; see [`Category`].
+    fn classify(&self) -> Category {
+        match *self {
+            ErrorCode::EOFWhileParsingList
+            | ErrorCode::EOFWhileParsingObject
+            | ErrorCode::EOFWhileParsingString
+            | ErrorCode::EOFWhileParsingValue => Category::Eof,
+            ErrorCode::UnknownField(_)
+            | ErrorCode::MissingField(_)
+            | ErrorCode::UnknownVariant
+            | ErrorCode::Message(_) => Category::Data,
+            _ => Category::Syntax,
+        }
+    }
 }
 
 impl fmt::Debug for ErrorCode {
@@ -74,19 +118,122 @@ impl fmt::Debug for ErrorCode {
             ErrorCode::UnexpectedEndOfHexEscape => "unexpected end of hex escape".fmt(f),
             ErrorCode::UnknownVariant => "unknown variant".fmt(f),
             ErrorCode::UnrecognizedHex => "invalid \\u escape (unrecognized hex)".fmt(f),
+            ErrorCode::InvalidBase64 => "invalid base64".fmt(f),
+            ErrorCode::Message(ref msg) => f.write_str(msg),
         }
     }
 }
 
+/// The broad category an [`Error`] falls into, for callers that want to
+/// react to a kind of failure rather than match on every `ErrorCode`.
+///
+/// This lets a caller reading from a socket or a partially written file
+/// tell "truncated stream, read more and retry" (`Eof`) apart from
+/// "malformed input, give up" (`Syntax`) or "well-formed JSON that doesn't
+/// match the target type" (`Data`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Category {
+    /// Failure to read or write bytes on an I/O stream.
+    Io,
+    /// Input that is not syntactically valid JSON.
+    Syntax,
+    /// Input that is syntactically valid JSON but does not match the
+    /// structure expected by the target type, such as a missing or
+    /// unknown field, or an unknown enum variant.
+    Data,
+    /// The input ended while more data was still expected, such as inside
+    /// a string, list, or object.
+    ///
+    /// Unlike other `Syntax` errors, this one is generally recoverable by
+    /// reading more data before retrying, which is why it gets its own
+    /// category instead of folding into `Syntax`.
+    Eof,
+}
+
 #[derive(Debug)]
 pub enum Error {
-    /// msg, line, col
-    SyntaxError(ErrorCode, usize, usize),
+    /// msg, line, col, byte offset
+    SyntaxError(ErrorCode, usize, usize, usize),
     IoError(io::Error),
     MissingFieldError(&'static str),
     FromUtf8Error(FromUtf8Error),
 }
 
+impl Error {
+    /// Classify the error as an I/O error, a syntax error, a data-model
+    /// error, or an unexpected end of input.
+    pub fn classify(&self) -> Category {
+        match *self {
+            Error::SyntaxError(ref code, ..) => code.classify(),
+            Error::IoError(_) => Category::Io,
+            Error::MissingFieldError(_) => Category::Data,
+            Error::FromUtf8Error(_) => Category::Syntax,
+        }
+    }
+
+    /// Returns true if this error was caused by a failure to read or write
+    /// bytes on an I/O stream.
+    pub fn is_io(&self) -> bool {
+        self.classify() == Category::Io
+    }
+
+    /// Returns true if this error was caused by input that was not
+    /// syntactically valid JSON.
+    pub fn is_syntax(&self) -> bool {
+        self.classify() == Category::Syntax
+    }
+
+    /// Returns true if this error was caused by input that was
+    /// syntactically valid JSON but did not match the structure expected
+    /// by the target type.
+    pub fn is_data(&self) -> bool {
+        self.classify() == Category::Data
+    }
+
+    /// Returns true if this error was caused by a premature end of input.
+    ///
+    /// This is the case in which a truncated stream can be distinguished
+    /// from a malformed one, so that callers can choose to wait for more
+    /// data and retry instead of giving up.
+    pub fn is_eof(&self) -> bool {
+        self.classify() == Category::Eof
+    }
+
+    /// The one-indexed line number at which the error was detected.
+    ///
+    /// Returns 0 if the error was not associated with a particular
+    /// location, such as an `io::Error`.
+    pub fn line(&self) -> usize {
+        match *self {
+            Error::SyntaxError(_, line, _, _) => line,
+            _ => 0,
+        }
+    }
+
+    /// The one-indexed column number at which the error was detected.
+    ///
+    /// Returns 0 if the error was not associated with a particular
+    /// location, such as an `io::Error`.
+    pub fn column(&self) -> usize {
+        match *self {
+            Error::SyntaxError(_, _, column, _) => column,
+            _ => 0,
+        }
+    }
+
+    /// The zero-indexed byte offset into the input at which the error was
+    /// detected.
+    ///
+    /// Returns 0 if the error was not associated with a particular
+    /// location, such as an `io::Error`.
+    pub fn byte_offset(&self) -> usize {
+        match *self {
+            Error::SyntaxError(_, _, _, byte_offset) => byte_offset,
+            _ => 0,
+        }
+    }
+}
+
 impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
@@ -110,7 +257,7 @@ impl error::Error for Error {
 impl fmt::Display for Error {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::SyntaxError(ref code, line, col) => {
+            Error::SyntaxError(ref code, line, col, _) => {
                 write!(fmt, "{:?} at line {} column {}", code, line, col)
             }
             Error::IoError(ref error) => fmt::Display::fmt(error, fmt),
@@ -138,16 +285,23 @@ impl From<de::value::Error> for Error {
     fn from(error: de::value::Error) -> Error {
         match error {
             de::value::Error::SyntaxError => {
-                Error::SyntaxError(ErrorCode::ExpectedSomeValue, 0, 0)
+                Error::SyntaxError(ErrorCode::ExpectedSomeValue, 0, 0, 0)
             }
             de::value::Error::EndOfStreamError => {
                 de::Error::end_of_stream()
             }
             de::value::Error::UnknownFieldError(field) => {
-                Error::SyntaxError(ErrorCode::UnknownField(field), 0, 0)
+                Error::SyntaxError(ErrorCode::UnknownField(field), 0, 0, 0)
             }
             de::value::Error::MissingFieldError(field) => {
-                de::Error::missing_field(field)
+                // Unlike `unknown_field`/`missing_field` raised by this
+                // crate's own text `Deserializer`, this conversion isn't
+                // driven by `parse_whitespace`, so there is no current
+                // position to report -- match the `UnknownFieldError` arm
+                // above rather than picking up whatever position happens
+                // to be left over in the thread-local from an unrelated
+                // parse.
+                Error::SyntaxError(ErrorCode::MissingField(field), 0, 0, 0)
             }
         }
     }
@@ -155,21 +309,91 @@ impl From<de::value::Error> for Error {
 
 impl de::Error for Error {
     fn syntax(_: &str) -> Error {
-        Error::SyntaxError(ErrorCode::ExpectedSomeValue, 0, 0)
+        Error::SyntaxError(ErrorCode::ExpectedSomeValue, 0, 0, 0)
     }
 
     fn end_of_stream() -> Error {
-        Error::SyntaxError(ErrorCode::EOFWhileParsingValue, 0, 0)
+        Error::SyntaxError(ErrorCode::EOFWhileParsingValue, 0, 0, 0)
     }
 
     fn unknown_field(field: &str) -> Error {
-        Error::SyntaxError(ErrorCode::UnknownField(String::from(field)), 0, 0)
+        let (line, column, byte_offset) = last_position();
+        Error::SyntaxError(
+            ErrorCode::UnknownField(String::from(field)),
+            line,
+            column,
+            byte_offset,
+        )
     }
 
     fn missing_field(field: &'static str) -> Error {
-        Error::MissingFieldError(field)
+        let (line, column, byte_offset) = last_position();
+        Error::SyntaxError(ErrorCode::MissingField(field), line, column, byte_offset)
+    }
+
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error::SyntaxError(ErrorCode::Message(msg.to_string()), 0, 0, 0)
     }
 }
 
 /// Helper alias for `Result` objects that return a JSON `Error`.
 pub type Result<T> = result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::{Category, Error, ErrorCode};
+
+    #[test]
+    fn classify_eof_errors() {
+        let err = Error::SyntaxError(ErrorCode::EOFWhileParsingString, 1, 1, 0);
+        assert_eq!(err.classify(), Category::Eof);
+        assert!(err.is_eof());
+    }
+
+    #[test]
+    fn classify_data_errors() {
+        let err = Error::SyntaxError(ErrorCode::UnknownField("x".into()), 3, 5, 20);
+        assert_eq!(err.classify(), Category::Data);
+        assert!(err.is_data());
+
+        assert_eq!(Error::MissingFieldError("y").classify(), Category::Data);
+    }
+
+    #[test]
+    fn classify_syntax_errors() {
+        let err = Error::SyntaxError(ErrorCode::ExpectedColon, 2, 4, 10);
+        assert_eq!(err.classify(), Category::Syntax);
+        assert!(err.is_syntax());
+    }
+
+    #[test]
+    fn byte_offset_reports_the_constructed_position() {
+        let err = Error::SyntaxError(ErrorCode::InvalidNumber, 2, 4, 17);
+        assert_eq!(err.line(), 2);
+        assert_eq!(err.column(), 4);
+        assert_eq!(err.byte_offset(), 17);
+    }
+
+    #[test]
+    fn custom_errors_classify_as_data() {
+        use serde::de::Error as DeError;
+
+        let err = Error::custom("not a valid widget");
+        assert!(err.is_data());
+        assert_eq!(err.to_string(), "not a valid widget at line 0 column 0");
+    }
+
+    #[test]
+    fn unknown_and_missing_field_report_the_last_recorded_position() {
+        use serde::de::Error as DeError;
+        use super::set_position;
+
+        set_position(3, 9, 42);
+
+        let err = Error::unknown_field("surprise");
+        assert_eq!((err.line(), err.column(), err.byte_offset()), (3, 9, 42));
+
+        let err = Error::missing_field("required");
+        assert_eq!((err.line(), err.column(), err.byte_offset()), (3, 9, 42));
+    }
+}
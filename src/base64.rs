@@ -0,0 +1,309 @@
+//! A `serde(with = "...")` adapter that carries `&[u8]`/`Vec<u8>` fields
+//! through JSON as base64-encoded strings.
+//!
+//! JSON has no native byte-string type, so without this a `Vec<u8>` field
+//! round-trips as an array of numbers (`[1,2,3,...]`), which is both wordy
+//! on the wire and slow to parse. Opting a field into this module instead
+//! serializes it as a single base64 string:
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_json;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Blob {
+//!     #[serde(with = "serde_json::base64")]
+//!     data: Vec<u8>,
+//! }
+//!
+//! # fn main() {
+//! let blob = Blob { data: vec![0xDE, 0xAD, 0xBE, 0xEF] };
+//! assert_eq!(serde_json::to_string(&blob).unwrap(), r#"{"data":"3q2+7w=="}"#);
+//! # }
+//! ```
+//!
+//! The top-level `serialize`/`deserialize` functions use the standard
+//! alphabet (`+`, `/`); use the [`url_safe`] sub-module for the URL- and
+//! filename-safe alphabet (`-`, `_`) instead.
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+
+use error::{Error, ErrorCode};
+
+/// The message `Base64Visitor::visit_str` gives `de::Error::custom` when
+/// `decode` rejects the string. `decode_field` looks for this exact text to
+/// tell "the string wasn't valid base64" apart from "the field wasn't a
+/// string at all", which the deserializer reports as its own, differently
+/// shaped type error.
+const INVALID_BASE64_MESSAGE: &str = "invalid base64";
+
+const STANDARD_ALPHABET: &'static [u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE_ALPHABET: &'static [u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn encode(data: &[u8], alphabet: &[u8; 64]) -> String {
+    let mut out = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0];
+        let b1 = *chunk.get(1).unwrap_or(&0);
+        let b2 = *chunk.get(2).unwrap_or(&0);
+
+        out.push(alphabet[(b0 >> 2) as usize] as char);
+        out.push(alphabet[((b0 << 4 | b1 >> 4) & 0x3f) as usize] as char);
+        out.push(if chunk.len() > 1 {
+            alphabet[((b1 << 2 | b2 >> 6) & 0x3f) as usize] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            alphabet[(b2 & 0x3f) as usize] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+fn decode_char(c: u8, alphabet: &[u8; 64]) -> Option<u8> {
+    alphabet.iter().position(|&a| a == c).map(|i| i as u8)
+}
+
+fn decode(data: &str, alphabet: &[u8; 64]) -> ::std::result::Result<Vec<u8>, ()> {
+    let data = data.trim_end_matches('=');
+    // A valid (unpadded) base64 body never leaves a single leftover
+    // character: that would mean a final sextet contributing only 6 bits
+    // with no partner to round out a whole byte.
+    if data.len() % 4 == 1 {
+        return Err(());
+    }
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    let mut bits: u32 = 0;
+    let mut num_bits = 0u32;
+    for b in data.bytes() {
+        let sextet = decode_char(b, alphabet).ok_or(())?;
+        bits = (bits << 6) | sextet as u32;
+        num_bits += 6;
+        if num_bits >= 8 {
+            num_bits -= 8;
+            out.push((bits >> num_bits) as u8);
+        }
+    }
+    // Any bits left over belong to padding and must be zero; a nonzero
+    // leftover means the input encoded more data than its length admits.
+    if bits & ((1 << num_bits) - 1) != 0 {
+        return Err(());
+    }
+    Ok(out)
+}
+
+struct Base64Visitor<'a> {
+    alphabet: &'a [u8; 64],
+}
+
+impl<'de, 'a> Visitor<'de> for Base64Visitor<'a> {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a base64-encoded string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> ::std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        decode(v, self.alphabet).map_err(|()| de::Error::custom(INVALID_BASE64_MESSAGE))
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> ::std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_str(v)
+    }
+
+    fn visit_string<E>(self, v: String) -> ::std::result::Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        self.visit_str(&v)
+    }
+}
+
+/// Run `deserializer` through a `Base64Visitor` for `alphabet`, relabeling
+/// a failed `decode` as [`ErrorCode::InvalidBase64`] without disturbing any
+/// other error, such as the field not being a string in the first place.
+///
+/// Shared by the standard and [`url_safe`] `deserialize` functions so the
+/// relabeling only needs to live in one place.
+fn decode_field<'de, D>(
+    deserializer: D,
+    alphabet: &'static [u8; 64],
+) -> ::std::result::Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de, Error = Error>,
+{
+    deserializer
+        .deserialize_str(Base64Visitor { alphabet })
+        .map_err(|e| match e {
+            Error::SyntaxError(ErrorCode::Message(ref msg), ..) if msg == INVALID_BASE64_MESSAGE => {
+                Error::SyntaxError(ErrorCode::InvalidBase64, 0, 0, 0)
+            }
+            e => e,
+        })
+}
+
+/// Decode `&[u8]`/`Vec<u8>` from a base64-encoded JSON string. See the
+/// [module documentation](index.html) for use with `#[serde(with = "...")]`.
+pub fn deserialize<'de, D>(deserializer: D) -> ::std::result::Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de, Error = Error>,
+{
+    decode_field(deserializer, STANDARD_ALPHABET)
+}
+
+/// Encode `&[u8]` as a base64 JSON string. See the
+/// [module documentation](index.html) for use with `#[serde(with = "...")]`.
+pub fn serialize<S>(data: &[u8], serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&encode(data, STANDARD_ALPHABET))
+}
+
+/// The URL- and filename-safe base64 alphabet (`-`, `_` in place of `+`,
+/// `/`), for use as `#[serde(with = "serde_json::base64::url_safe")]`.
+pub mod url_safe {
+    use super::{decode_field, encode, URL_SAFE_ALPHABET};
+    use error::Error;
+    use serde::de::Deserializer;
+    use serde::ser::Serializer;
+
+    /// Decode `&[u8]`/`Vec<u8>` from a URL-safe base64-encoded JSON string.
+    pub fn deserialize<'de, D>(deserializer: D) -> ::std::result::Result<Vec<u8>, D::Error>
+    where
+        D: Deserializer<'de, Error = Error>,
+    {
+        decode_field(deserializer, URL_SAFE_ALPHABET)
+    }
+
+    /// Encode `&[u8]` as a URL-safe base64 JSON string.
+    pub fn serialize<S>(data: &[u8], serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&encode(data, URL_SAFE_ALPHABET))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decode_field, encode, STANDARD_ALPHABET};
+    use error::{Error, ErrorCode};
+    use serde::de::{self, Deserializer, Visitor};
+
+    #[test]
+    fn round_trips_arbitrary_lengths() {
+        for data in &[&b""[..], b"f", b"fo", b"foo", b"foob", b"fooba", b"foobar"] {
+            let encoded = encode(data, STANDARD_ALPHABET);
+            assert_eq!(decode(&encoded, STANDARD_ALPHABET).unwrap(), *data);
+        }
+    }
+
+    #[test]
+    fn encodes_with_standard_padding() {
+        assert_eq!(encode(b"\xDE\xAD\xBE\xEF", STANDARD_ALPHABET), "3q2+7w==");
+    }
+
+    #[test]
+    fn rejects_invalid_length() {
+        // 5 base64 characters can never decode to a whole number of bytes.
+        assert!(decode("ZZZZZ", STANDARD_ALPHABET).is_err());
+    }
+
+    #[test]
+    fn rejects_nonzero_padding_bits() {
+        // "/w==" decodes cleanly to one byte; "AB==" has the same length
+        // but its trailing sextet carries nonzero bits beyond that byte.
+        assert!(decode("/w==", STANDARD_ALPHABET).is_ok());
+        assert!(decode("AB==", STANDARD_ALPHABET).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_characters() {
+        assert!(decode("!!!!", STANDARD_ALPHABET).is_err());
+    }
+
+    /// Hands a single string straight to `deserialize_str`, standing in for
+    /// a real `Deserializer` so `decode_field` can be exercised without a
+    /// parser.
+    struct StrDeserializer<'a>(&'a str);
+
+    impl<'de, 'a> Deserializer<'de> for StrDeserializer<'a> {
+        type Error = Error;
+
+        fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            visitor.visit_str(self.0)
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    /// Reports a type mismatch no matter what it's asked for, standing in
+    /// for a `Deserializer` fed a non-string field such as `"data": 42`.
+    struct NotAStringDeserializer;
+
+    impl<'de> Deserializer<'de> for NotAStringDeserializer {
+        type Error = Error;
+
+        fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Error>
+        where
+            V: Visitor<'de>,
+        {
+            Err(de::Error::custom("invalid type: integer `42`, expected a string"))
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+        }
+    }
+
+    #[test]
+    fn decode_field_relabels_bad_content_as_invalid_base64() {
+        let err = decode_field(StrDeserializer("!!!!"), STANDARD_ALPHABET).unwrap_err();
+        match err {
+            Error::SyntaxError(ErrorCode::InvalidBase64, ..) => {}
+            other => panic!("expected InvalidBase64, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_field_leaves_type_errors_alone() {
+        let err = decode_field(NotAStringDeserializer, STANDARD_ALPHABET).unwrap_err();
+        match err {
+            Error::SyntaxError(ErrorCode::InvalidBase64, ..) => {
+                panic!("a type error should not be relabeled as InvalidBase64")
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The standard base64 alphabet (`+`, `/`), identical to the top-level
+/// `serialize`/`deserialize` in this module.
+pub mod standard {
+    pub use super::{deserialize, serialize};
+}
@@ -0,0 +1,182 @@
+//! The core token-at-a-time JSON deserializer, and the iterator it can
+//! produce over a sequence of values sharing one input.
+//!
+//! Most callers never name [`Deserializer`] directly; they go through
+//! [`::from_str`]/[`::from_slice`]/[`::from_reader`] in the crate root
+//! instead. It's exposed here so [`Deserializer::into_iter`] can hand back
+//! a [`StreamDeserializer`] for reading back-to-back or newline-delimited
+//! values off of one `Read`.
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::Deserialize;
+
+use error::{self, Result};
+use read::Read;
+
+/// Walks a `Read` one token at a time, handing parsed values to whatever
+/// `serde::de::Visitor` asks for them.
+pub struct Deserializer<R> {
+    read: R,
+    line: usize,
+    column: usize,
+}
+
+impl<'de, R> Deserializer<R>
+where
+    R: Read<'de>,
+{
+    /// Create a deserializer that reads from `read`, starting at line 1,
+    /// column 0.
+    pub fn new(read: R) -> Self {
+        Deserializer {
+            read: read,
+            line: 1,
+            column: 0,
+        }
+    }
+
+    /// Skip whitespace and return the first non-whitespace byte peeked, or
+    /// `None` at a clean EOF. Shared by value parsing and by
+    /// [`StreamDeserializer`], which uses it to tell "no more values" apart
+    /// from "another value starts here" without consuming anything.
+    ///
+    /// Every token boundary this passes through records the current
+    /// position, so that an `unknown_field`/`missing_field` error raised
+    /// while deserializing the value that starts here can report where it
+    /// happened instead of `0, 0, 0` -- those two `de::Error` methods take
+    /// no position argument of their own.
+    pub(crate) fn parse_whitespace(&mut self) -> Result<Option<u8>> {
+        loop {
+            match try!(self.read.peek()) {
+                Some(b' ') | Some(b'\t') | Some(b'\r') => {
+                    self.read.discard();
+                    self.column += 1;
+                }
+                Some(b'\n') => {
+                    self.read.discard();
+                    self.line += 1;
+                    self.column = 0;
+                }
+                other => {
+                    error::set_position(self.line, self.column, self.read.byte_offset());
+                    return Ok(other);
+                }
+            }
+        }
+    }
+
+    /// The byte offset into the input the reader has consumed so far.
+    pub fn byte_offset(&self) -> usize {
+        self.read.byte_offset()
+    }
+
+    /// Turn this deserializer into an iterator over a sequence of `T`
+    /// sharing the same input, such as NDJSON or back-to-back values with
+    /// no separator (`{}{}[]`).
+    pub fn into_iter<T>(self) -> StreamDeserializer<'de, R, T>
+    where
+        T: Deserialize<'de>,
+    {
+        StreamDeserializer {
+            de: self,
+            offset: 0,
+            failed: false,
+            output: PhantomData,
+            lifetime: PhantomData,
+        }
+    }
+}
+
+/// Iterator that deserializes a sequence of JSON values from an underlying
+/// `Read`, stopping cleanly at EOF.
+///
+/// Values may be separated by whitespace (as in NDJSON, one value per
+/// line) or may directly abut one another, since every JSON value other
+/// than a bare number is self-delimiting. A bare number followed
+/// immediately by another value without intervening whitespace is
+/// ambiguous (`12` could continue as `123`) and is treated as one value
+/// continuing to consume digits, matching how a single `from_str` call
+/// already behaves.
+///
+/// Construct one with [`Deserializer::into_iter`], or the
+/// `from_str`/`from_slice`/`from_reader` functions in the `stream` module.
+pub struct StreamDeserializer<'de, R, T> {
+    de: Deserializer<R>,
+    offset: usize,
+    failed: bool,
+    output: PhantomData<T>,
+    lifetime: PhantomData<&'de ()>,
+}
+
+impl<'de, R, T> StreamDeserializer<'de, R, T>
+where
+    R: Read<'de>,
+    T: Deserialize<'de>,
+{
+    /// Create a `StreamDeserializer` driving `read` to produce a sequence
+    /// of `T`.
+    pub fn new(read: R) -> Self {
+        Deserializer::new(read).into_iter()
+    }
+
+    /// The byte offset, relative to the start of the input, at which the
+    /// value most recently returned by `next()` started (or, after a
+    /// parse failure, at which the failure was detected).
+    ///
+    /// Callers can use this to resynchronize after an error, for example
+    /// by skipping to the next newline past this offset and resuming.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'de, R, T> Iterator for StreamDeserializer<'de, R, T>
+where
+    R: Read<'de>,
+    T: Deserialize<'de>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed {
+            return None;
+        }
+
+        // Skip whitespace between values without committing to reading
+        // any further, so a trailing newline after the last value does
+        // not get mistaken for the start of another one. This reuses the
+        // same whitespace-skipping the deserializer already does between
+        // tokens, rather than duplicating it here.
+        match self.de.parse_whitespace() {
+            Ok(None) => return None,
+            Ok(Some(_)) => {}
+            Err(e) => {
+                self.failed = true;
+                return Some(Err(e));
+            }
+        }
+
+        self.offset = self.de.byte_offset();
+
+        let result = T::deserialize(&mut self.de);
+
+        Some(match result {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                self.failed = true;
+                self.offset = self.de.byte_offset();
+                Err(e)
+            }
+        })
+    }
+}
+
+impl<'de, R, T> fmt::Debug for StreamDeserializer<'de, R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StreamDeserializer")
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
@@ -6,8 +6,10 @@ use std::ops::Deref;
 use serde::ser::{Serialize, Serializer, SerializeStruct};
 use serde::de::{self, Deserialize, Deserializer, DeserializeSeed, IntoDeserializer, MapAccess, Unexpected, Visitor};
 use serde::de::value::BorrowedStrDeserializer;
+use serde::de::IgnoredAny;
 
 use error::Error;
+use value::Value;
 
 /// Reference to a range of bytes encompassing a single valid JSON value in the
 /// input data.
@@ -187,6 +189,65 @@ impl RawSlice {
     }
 }
 
+impl RawValue {
+    /// Build a `RawValue` holding the JSON text `null`.
+    pub fn null() -> Self {
+        RawValue::from_inner("null".to_owned().into_boxed_str())
+    }
+
+    /// Parse and validate `s` as a `RawValue`, taking ownership of the
+    /// string.
+    ///
+    /// This parses `s` once to confirm it is exactly one complete JSON
+    /// value with no trailing characters, the same check `from_str`
+    /// performs on any other type. The text is otherwise stored verbatim,
+    /// so it is not re-escaped, re-quoted, or reformatted when the
+    /// `RawValue` is later serialized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// extern crate serde_json;
+    ///
+    /// use serde_json::value::RawValue;
+    ///
+    /// fn main() -> serde_json::Result<()> {
+    ///     // A blob of JSON text obtained from somewhere else, spliced
+    ///     // into a new envelope without being re-escaped.
+    ///     let cached = RawValue::from_string(r#"{"id":1,"name":"a"}"#.to_owned())?;
+    ///     assert_eq!(cached.get(), r#"{"id":1,"name":"a"}"#);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_string(s: String) -> ::error::Result<Self> {
+        let _: IgnoredAny = ::from_str(&s)?;
+        Ok(RawValue::from_inner(s.into_boxed_str()))
+    }
+
+    /// Parse and validate `s` as a `RawValue`, copying the string.
+    ///
+    /// See [`RawValue::from_string`] for the validation performed.
+    pub fn from_str(s: &str) -> ::error::Result<Self> {
+        RawValue::from_string(s.to_owned())
+    }
+
+    /// Convert an already-parsed `Value` into a `RawValue` holding its
+    /// serialized JSON text.
+    ///
+    /// Since `value` is known to serialize to valid JSON, this never fails.
+    pub fn from_value(value: &Value) -> Self {
+        let s = ::to_string(value).expect("serializing a Value to a String cannot fail");
+        RawValue::from_inner(s.into_boxed_str())
+    }
+
+    /// Access the JSON text underlying this `RawValue`.
+    ///
+    /// This is the owned counterpart to [`RawSlice::as_ref`].
+    pub fn get(&self) -> &str {
+        &self.owned.borrowed
+    }
+}
+
 impl Display for RawSlice {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(&self.borrowed)
@@ -445,3 +506,42 @@ impl<'de> MapAccess<'de> for BorrowedRawDeserializer<'de> {
         seed.deserialize(BorrowedStrDeserializer::new(self.raw_value.take().unwrap()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::RawValue;
+
+    #[test]
+    fn round_trips_the_input_text_verbatim() {
+        let raw = RawValue::from_str(r#"{"id":1,"name":"a"}"#).unwrap();
+        assert_eq!(raw.get(), r#"{"id":1,"name":"a"}"#);
+    }
+
+    #[test]
+    fn from_string_keeps_the_owned_string_verbatim() {
+        let raw = RawValue::from_string("[1,2,3]".to_owned()).unwrap();
+        assert_eq!(raw.get(), "[1,2,3]");
+    }
+
+    #[test]
+    fn null_builds_the_literal_null() {
+        assert_eq!(RawValue::null().get(), "null");
+    }
+
+    #[test]
+    fn rejects_trailing_characters() {
+        assert!(RawValue::from_str("{}x").is_err());
+        assert!(RawValue::from_string("1 2".to_owned()).is_err());
+    }
+
+    #[test]
+    fn rejects_empty_input() {
+        assert!(RawValue::from_str("").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        assert!(RawValue::from_str("{").is_err());
+        assert!(RawValue::from_str("{\"a\":}").is_err());
+    }
+}
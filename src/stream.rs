@@ -0,0 +1,73 @@
+//! Free functions for reading a sequence of self-delimiting JSON values
+//! sharing one input, such as newline-delimited JSON (NDJSON) or
+//! back-to-back values with no separator at all (`{}{}[]`).
+//!
+//! This turns the crate into a usable reader for log streams and chunked
+//! RPC transports where many documents share a socket: each call to
+//! `next()` on the returned [`StreamDeserializer`] parses exactly one
+//! value and stops before the bytes belonging to the next one, so the
+//! underlying reader is left positioned to read whatever comes after.
+//!
+//! `StreamDeserializer` itself lives in [`de`](../de/index.html), next to
+//! the `Deserializer` it wraps, since it reaches into that deserializer's
+//! crate-private whitespace-skipping.
+
+use de::StreamDeserializer;
+use read;
+use serde::de::Deserialize;
+
+/// Iterate over a sequence of whitespace- or newline-separated (or bare,
+/// back-to-back) JSON values borrowed from a `&str`.
+pub fn from_str<'de, T>(s: &'de str) -> StreamDeserializer<'de, read::StrRead<'de>, T>
+where
+    T: Deserialize<'de>,
+{
+    StreamDeserializer::new(read::StrRead::new(s))
+}
+
+/// Iterate over a sequence of JSON values borrowed from a `&[u8]` slice of
+/// UTF-8 text.
+pub fn from_slice<'de, T>(bytes: &'de [u8]) -> StreamDeserializer<'de, read::SliceRead<'de>, T>
+where
+    T: Deserialize<'de>,
+{
+    StreamDeserializer::new(read::SliceRead::new(bytes))
+}
+
+/// Iterate over a sequence of JSON values read from an `io::Read`, such as
+/// a socket carrying NDJSON.
+///
+/// Each yielded `T` is fully owned, since the reader gives no borrowed
+/// data to tie a lifetime to.
+pub fn from_reader<R, T>(reader: R) -> StreamDeserializer<'static, read::IoRead<R>, T>
+where
+    R: ::std::io::Read,
+    T: ::serde::de::DeserializeOwned,
+{
+    StreamDeserializer::new(read::IoRead::new(reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_str;
+    use value::Value;
+
+    #[test]
+    fn reads_back_to_back_values_with_no_separator() {
+        let values: Vec<Value> = from_str("{}{}[]").map(|r| r.unwrap()).collect();
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn reads_newline_delimited_values() {
+        let values: Vec<Value> = from_str("1\n2\n3\n").map(|r| r.unwrap()).collect();
+        assert_eq!(values, vec![Value::from(1), Value::from(2), Value::from(3)]);
+    }
+
+    #[test]
+    fn stops_cleanly_on_trailing_whitespace() {
+        let mut iter = from_str::<Value>("1   \n\t ");
+        assert_eq!(iter.next().unwrap().unwrap(), Value::from(1));
+        assert!(iter.next().is_none());
+    }
+}
@@ -0,0 +1,818 @@
+//! Stream a [`Deserializer`] straight into a [`Serializer`] without ever
+//! materializing an intermediate [`Value`](::value::Value) tree.
+//!
+//! Every callback the input hands to a `Visitor` is forwarded immediately to
+//! the matching method on the output `Serializer` (or `SerializeMap`/
+//! `SerializeSeq`), so a seq or map is copied element-by-element instead of
+//! being collected first. This keeps memory bounded when reshaping a large
+//! document, at the cost of re-parsing and re-serializing every value along
+//! the way — there is no fast path that copies a sub-document's bytes
+//! verbatim.
+//!
+//! ```
+//! # extern crate serde_json;
+//! # fn example() -> Result<(), Box<::std::error::Error>> {
+//! use serde_json::transcode;
+//!
+//! let mut de = serde_json::Deserializer::from_str(r#"{"a": 1, "b": [2, 3]}"#);
+//! let mut buf = Vec::new();
+//! let mut ser = serde_json::Serializer::new(&mut buf);
+//! transcode::transcode(&mut de, &mut ser)?;
+//! assert_eq!(buf, br#"{"a":1,"b":[2,3]}"#.to_vec());
+//! # Ok(())
+//! # }
+//! # fn main() { example().unwrap(); }
+//! ```
+
+use std::cell::RefCell;
+use std::error;
+use std::fmt::{self, Display};
+use std::io;
+
+use serde::de::{DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use error::{Error, Result};
+
+/// Either half of a transcode failed: either the input could not be parsed
+/// (`De`) or the output could not be written (`Ser`).
+///
+/// Only a failure at the top level of the input, or in the structure of a
+/// sequence or map (including its keys), is reported as `De`. A failure
+/// while parsing an element of a sequence or the value of a map entry is
+/// reported as `Ser`, holding the serializer's error built from the
+/// deserializer's message.
+#[derive(Debug)]
+pub enum TranscodeError<D, S> {
+    /// The deserializer reported an error while reading the input.
+    De(D),
+    /// The serializer reported an error while writing the output.
+    Ser(S),
+}
+
+impl<D, S> Display for TranscodeError<D, S>
+where
+    D: Display,
+    S: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TranscodeError::De(ref e) => Display::fmt(e, f),
+            TranscodeError::Ser(ref e) => Display::fmt(e, f),
+        }
+    }
+}
+
+impl<D, S> error::Error for TranscodeError<D, S>
+where
+    D: error::Error,
+    S: error::Error,
+{
+    fn description(&self) -> &str {
+        match *self {
+            TranscodeError::De(ref e) => e.description(),
+            TranscodeError::Ser(ref e) => e.description(),
+        }
+    }
+}
+
+/// Drive `deserializer` directly into `serializer`, copying each value as it
+/// is parsed rather than collecting it into a `Value` first.
+///
+/// On success this returns whatever the serializer produces as its `Ok`
+/// value (`()` for a writer-backed serializer).
+pub fn transcode<'de, D, S>(
+    deserializer: D,
+    serializer: S,
+) -> ::std::result::Result<S::Ok, TranscodeError<D::Error, S::Error>>
+where
+    D: Deserializer<'de>,
+    S: Serializer,
+{
+    match (Transcoder { ser: serializer }).deserialize(deserializer) {
+        Ok(Ok(ok)) => Ok(ok),
+        Ok(Err(ser_err)) => Err(TranscodeError::Ser(ser_err)),
+        Err(de_err) => Err(TranscodeError::De(de_err)),
+    }
+}
+
+/// Convenience wrapper around [`transcode`] that writes the transcoded
+/// output to any `io::Write`, fixing the input to this crate's own `Error`
+/// type.
+pub fn transcode_to_writer<'de, D, W>(deserializer: D, writer: W) -> Result<()>
+where
+    D: Deserializer<'de, Error = Error>,
+    W: io::Write,
+{
+    let mut ser = ::ser::Serializer::new(writer);
+    match transcode(deserializer, &mut ser) {
+        Ok(()) => Ok(()),
+        Err(TranscodeError::De(e)) | Err(TranscodeError::Ser(e)) => Err(e),
+    }
+}
+
+/// Convenience wrapper around [`transcode`] that collects the transcoded
+/// output into a `String`, fixing the input to this crate's own `Error`
+/// type.
+pub fn transcode_to_string<'de, D>(deserializer: D) -> Result<String>
+where
+    D: Deserializer<'de, Error = Error>,
+{
+    let mut buf = Vec::new();
+    transcode_to_writer(deserializer, &mut buf)?;
+    Ok(String::from_utf8(buf).expect("a Serializer only ever writes well-formed UTF-8"))
+}
+
+/// `DeserializeSeed`/`Visitor` pair that threads a `Serializer` through as
+/// seed state and forwards every visitor callback straight into it.
+struct Transcoder<S> {
+    ser: S,
+}
+
+impl<'de, S> DeserializeSeed<'de> for Transcoder<S>
+where
+    S: Serializer,
+{
+    type Value = ::std::result::Result<S::Ok, S::Error>;
+
+    fn deserialize<D>(self, deserializer: D) -> ::std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, S> Visitor<'de> for Transcoder<S>
+where
+    S: Serializer,
+{
+    type Value = ::std::result::Result<S::Ok, S::Error>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> ::std::result::Result<Self::Value, E> {
+        Ok(self.ser.serialize_bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> ::std::result::Result<Self::Value, E> {
+        Ok(self.ser.serialize_i64(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> ::std::result::Result<Self::Value, E> {
+        Ok(self.ser.serialize_u64(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> ::std::result::Result<Self::Value, E> {
+        Ok(self.ser.serialize_f64(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> ::std::result::Result<Self::Value, E> {
+        Ok(self.ser.serialize_str(v))
+    }
+
+    fn visit_string<E>(self, v: String) -> ::std::result::Result<Self::Value, E> {
+        Ok(self.ser.serialize_str(&v))
+    }
+
+    fn visit_unit<E>(self) -> ::std::result::Result<Self::Value, E> {
+        Ok(self.ser.serialize_unit())
+    }
+
+    fn visit_none<E>(self) -> ::std::result::Result<Self::Value, E> {
+        Ok(self.ser.serialize_none())
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> ::std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> ::std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> ::std::result::Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut ser_seq = match self.ser.serialize_seq(seq.size_hint()) {
+            Ok(ser_seq) => ser_seq,
+            Err(err) => return Ok(Err(err)),
+        };
+        while let Some(result) = seq.next_element_seed(SeqElementTranscoder {
+            ser_seq: &mut ser_seq,
+        })? {
+            if let Err(err) = result {
+                return Ok(Err(err));
+            }
+        }
+        Ok(ser_seq.end())
+    }
+
+    fn visit_map<A>(self, mut map: A) -> ::std::result::Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut ser_map = match self.ser.serialize_map(map.size_hint()) {
+            Ok(ser_map) => ser_map,
+            Err(err) => return Ok(Err(err)),
+        };
+        loop {
+            let key = match map.next_key::<String>()? {
+                Some(key) => key,
+                None => break,
+            };
+            if let Err(err) = ser_map.serialize_key(&key) {
+                return Ok(Err(err));
+            }
+            if let Err(err) = map.next_value_seed(MapValueTranscoder {
+                ser_map: &mut ser_map,
+            })? {
+                return Ok(Err(err));
+            }
+        }
+        Ok(ser_map.end())
+    }
+}
+
+/// Serializes one already-in-hand value by forwarding to a `SerializeSeq`
+/// or `SerializeMap`, used where those traits require a `T: Serialize`
+/// rather than letting us drive the underlying serializer directly.
+struct ReserializeSeed<'de, D> {
+    de: RefCell<Option<D>>,
+    marker: ::std::marker::PhantomData<&'de ()>,
+}
+
+impl<'de, D> ReserializeSeed<'de, D> {
+    fn new(de: D) -> Self {
+        ReserializeSeed {
+            de: RefCell::new(Some(de)),
+            marker: ::std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'de, D> Serialize for ReserializeSeed<'de, D>
+where
+    D: Deserializer<'de>,
+{
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let de = self.de.borrow_mut().take().expect("transcoded element serialized twice");
+        match (Transcoder { ser: serializer }).deserialize(de) {
+            Ok(result) => result,
+            Err(de_err) => Err(ser::Error::custom(de_err.to_string())),
+        }
+    }
+}
+
+struct SeqElementTranscoder<'a, T: 'a> {
+    ser_seq: &'a mut T,
+}
+
+impl<'de, 'a, T> DeserializeSeed<'de> for SeqElementTranscoder<'a, T>
+where
+    T: SerializeSeq + 'a,
+{
+    type Value = ::std::result::Result<(), T::Error>;
+
+    fn deserialize<D>(self, deserializer: D) -> ::std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(self.ser_seq.serialize_element(&ReserializeSeed::new(deserializer)))
+    }
+}
+
+struct MapValueTranscoder<'a, T: 'a> {
+    ser_map: &'a mut T,
+}
+
+impl<'de, 'a, T> DeserializeSeed<'de> for MapValueTranscoder<'a, T>
+where
+    T: SerializeMap + 'a,
+{
+    type Value = ::std::result::Result<(), T::Error>;
+
+    fn deserialize<D>(self, deserializer: D) -> ::std::result::Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(self.ser_map.serialize_value(&ReserializeSeed::new(deserializer)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as StdError;
+    use std::fmt;
+
+    use serde::de::{self, DeserializeSeed, Deserializer as DeDeserializer, MapAccess, SeqAccess, Visitor};
+    use serde::ser::{self, Impossible, Serialize, SerializeMap, SerializeSeq, Serializer as SerSerializer};
+
+    use super::{transcode, TranscodeError};
+
+    #[derive(Debug)]
+    struct TestError(String);
+
+    impl fmt::Display for TestError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    impl StdError for TestError {
+        fn description(&self) -> &str {
+            &self.0
+        }
+    }
+
+    impl ser::Error for TestError {
+        fn custom<T: fmt::Display>(msg: T) -> Self {
+            TestError(msg.to_string())
+        }
+    }
+
+    impl de::Error for TestError {
+        fn custom<T: fmt::Display>(msg: T) -> Self {
+            TestError(msg.to_string())
+        }
+    }
+
+    /// A minimal, self-describing "document" used only to drive `transcode`
+    /// in these tests, independent of this crate's own parser-backed
+    /// `Deserializer`.
+    enum TestValue {
+        Unit,
+        NoneValue,
+        Bool(bool),
+        I64(i64),
+        Str(&'static str),
+        Seq(Vec<TestValue>),
+        Map(Vec<(&'static str, TestValue)>),
+        Newtype(Box<TestValue>),
+        Fail,
+    }
+
+    struct TestSeqAccess {
+        iter: ::std::vec::IntoIter<TestValue>,
+    }
+
+    impl<'de> SeqAccess<'de> for TestSeqAccess {
+        type Error = TestError;
+
+        fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, TestError>
+        where
+            T: DeserializeSeed<'de>,
+        {
+            match self.iter.next() {
+                Some(v) => seed.deserialize(v).map(Some),
+                None => Ok(None),
+            }
+        }
+    }
+
+    struct TestMapAccess {
+        iter: ::std::vec::IntoIter<(&'static str, TestValue)>,
+        value: Option<TestValue>,
+    }
+
+    impl<'de> MapAccess<'de> for TestMapAccess {
+        type Error = TestError;
+
+        fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, TestError>
+        where
+            K: DeserializeSeed<'de>,
+        {
+            match self.iter.next() {
+                Some((k, v)) => {
+                    self.value = Some(v);
+                    seed.deserialize(TestValue::Str(k)).map(Some)
+                }
+                None => Ok(None),
+            }
+        }
+
+        fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, TestError>
+        where
+            V: DeserializeSeed<'de>,
+        {
+            seed.deserialize(self.value.take().expect("next_value_seed called before next_key_seed"))
+        }
+    }
+
+    impl<'de> DeDeserializer<'de> for TestValue {
+        type Error = TestError;
+
+        fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, TestError>
+        where
+            V: Visitor<'de>,
+        {
+            match self {
+                TestValue::Unit => visitor.visit_unit(),
+                TestValue::NoneValue => visitor.visit_none(),
+                TestValue::Bool(b) => visitor.visit_bool(b),
+                TestValue::I64(n) => visitor.visit_i64(n),
+                TestValue::Str(s) => visitor.visit_borrowed_str(s),
+                TestValue::Seq(items) => visitor.visit_seq(TestSeqAccess { iter: items.into_iter() }),
+                TestValue::Map(entries) => {
+                    visitor.visit_map(TestMapAccess { iter: entries.into_iter(), value: None })
+                }
+                TestValue::Newtype(inner) => visitor.visit_newtype_struct(*inner),
+                TestValue::Fail => Err(TestError("deserialization failed".to_owned())),
+            }
+        }
+
+        forward_to_deserialize_any! {
+            bool u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 f32 f64 char str string seq
+            bytes byte_buf map struct option unit newtype_struct ignored_any
+            unit_struct tuple_struct tuple enum identifier
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Tok {
+        Bool(bool),
+        I64(i64),
+        Str(String),
+        Unit,
+        None,
+        SeqStart,
+        SeqEnd,
+        MapStart,
+        MapEnd,
+    }
+
+    struct TokenSerializer<'a> {
+        tokens: &'a mut Vec<Tok>,
+    }
+
+    struct SeqCollector<'a> {
+        tokens: &'a mut Vec<Tok>,
+    }
+
+    impl<'a> SerializeSeq for SeqCollector<'a> {
+        type Ok = ();
+        type Error = TestError;
+
+        fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), TestError> {
+            value.serialize(TokenSerializer { tokens: &mut *self.tokens })
+        }
+
+        fn end(self) -> Result<(), TestError> {
+            self.tokens.push(Tok::SeqEnd);
+            Ok(())
+        }
+    }
+
+    struct MapCollector<'a> {
+        tokens: &'a mut Vec<Tok>,
+    }
+
+    impl<'a> SerializeMap for MapCollector<'a> {
+        type Ok = ();
+        type Error = TestError;
+
+        fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), TestError> {
+            key.serialize(TokenSerializer { tokens: &mut *self.tokens })
+        }
+
+        fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), TestError> {
+            value.serialize(TokenSerializer { tokens: &mut *self.tokens })
+        }
+
+        fn end(self) -> Result<(), TestError> {
+            self.tokens.push(Tok::MapEnd);
+            Ok(())
+        }
+    }
+
+    impl<'a> SerSerializer for TokenSerializer<'a> {
+        type Ok = ();
+        type Error = TestError;
+        type SerializeSeq = SeqCollector<'a>;
+        type SerializeTuple = Impossible<(), TestError>;
+        type SerializeTupleStruct = Impossible<(), TestError>;
+        type SerializeTupleVariant = Impossible<(), TestError>;
+        type SerializeMap = MapCollector<'a>;
+        type SerializeStruct = Impossible<(), TestError>;
+        type SerializeStructVariant = Impossible<(), TestError>;
+
+        fn serialize_bool(self, v: bool) -> Result<(), TestError> {
+            self.tokens.push(Tok::Bool(v));
+            Ok(())
+        }
+
+        fn serialize_i8(self, v: i8) -> Result<(), TestError> { self.serialize_i64(v as i64) }
+        fn serialize_i16(self, v: i16) -> Result<(), TestError> { self.serialize_i64(v as i64) }
+        fn serialize_i32(self, v: i32) -> Result<(), TestError> { self.serialize_i64(v as i64) }
+
+        fn serialize_i64(self, v: i64) -> Result<(), TestError> {
+            self.tokens.push(Tok::I64(v));
+            Ok(())
+        }
+
+        fn serialize_u8(self, v: u8) -> Result<(), TestError> { self.serialize_i64(v as i64) }
+        fn serialize_u16(self, v: u16) -> Result<(), TestError> { self.serialize_i64(v as i64) }
+        fn serialize_u32(self, v: u32) -> Result<(), TestError> { self.serialize_i64(v as i64) }
+        fn serialize_u64(self, v: u64) -> Result<(), TestError> { self.serialize_i64(v as i64) }
+
+        fn serialize_f32(self, v: f32) -> Result<(), TestError> { self.serialize_i64(v as i64) }
+        fn serialize_f64(self, v: f64) -> Result<(), TestError> { self.serialize_i64(v as i64) }
+
+        fn serialize_char(self, v: char) -> Result<(), TestError> {
+            self.serialize_str(&v.to_string())
+        }
+
+        fn serialize_str(self, v: &str) -> Result<(), TestError> {
+            self.tokens.push(Tok::Str(v.to_owned()));
+            Ok(())
+        }
+
+        fn serialize_bytes(self, _v: &[u8]) -> Result<(), TestError> {
+            Err(TestError("bytes unsupported in this test serializer".to_owned()))
+        }
+
+        fn serialize_none(self) -> Result<(), TestError> {
+            self.tokens.push(Tok::None);
+            Ok(())
+        }
+
+        fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), TestError> {
+            value.serialize(self)
+        }
+
+        fn serialize_unit(self) -> Result<(), TestError> {
+            self.tokens.push(Tok::Unit);
+            Ok(())
+        }
+
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<(), TestError> {
+            self.serialize_unit()
+        }
+
+        fn serialize_unit_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            variant: &'static str,
+        ) -> Result<(), TestError> {
+            self.serialize_str(variant)
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            value: &T,
+        ) -> Result<(), TestError> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _value: &T,
+        ) -> Result<(), TestError> {
+            Err(TestError("newtype variants unsupported in this test serializer".to_owned()))
+        }
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<SeqCollector<'a>, TestError> {
+            self.tokens.push(Tok::SeqStart);
+            Ok(SeqCollector { tokens: self.tokens })
+        }
+
+        fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, TestError> {
+            Err(TestError("tuples unsupported in this test serializer".to_owned()))
+        }
+
+        fn serialize_tuple_struct(
+            self,
+            _name: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleStruct, TestError> {
+            Err(TestError("tuple structs unsupported in this test serializer".to_owned()))
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleVariant, TestError> {
+            Err(TestError("tuple variants unsupported in this test serializer".to_owned()))
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<MapCollector<'a>, TestError> {
+            self.tokens.push(Tok::MapStart);
+            Ok(MapCollector { tokens: self.tokens })
+        }
+
+        fn serialize_struct(
+            self,
+            _name: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStruct, TestError> {
+            Err(TestError("structs unsupported in this test serializer".to_owned()))
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStructVariant, TestError> {
+            Err(TestError("struct variants unsupported in this test serializer".to_owned()))
+        }
+    }
+
+    macro_rules! always_fail {
+        ($name:ident($($arg:ident: $ty:ty),*)) => {
+            fn $name(self, $($arg: $ty),*) -> Result<(), TestError> {
+                Err(TestError("serializer failed".to_owned()))
+            }
+        };
+    }
+
+    /// A `Serializer` that fails on the very first write, used to exercise
+    /// `TranscodeError::Ser`.
+    struct AlwaysFailSerializer;
+
+    impl SerSerializer for AlwaysFailSerializer {
+        type Ok = ();
+        type Error = TestError;
+        type SerializeSeq = Impossible<(), TestError>;
+        type SerializeTuple = Impossible<(), TestError>;
+        type SerializeTupleStruct = Impossible<(), TestError>;
+        type SerializeTupleVariant = Impossible<(), TestError>;
+        type SerializeMap = Impossible<(), TestError>;
+        type SerializeStruct = Impossible<(), TestError>;
+        type SerializeStructVariant = Impossible<(), TestError>;
+
+        always_fail!(serialize_bool(v: bool));
+        always_fail!(serialize_i8(v: i8));
+        always_fail!(serialize_i16(v: i16));
+        always_fail!(serialize_i32(v: i32));
+        always_fail!(serialize_i64(v: i64));
+        always_fail!(serialize_u8(v: u8));
+        always_fail!(serialize_u16(v: u16));
+        always_fail!(serialize_u32(v: u32));
+        always_fail!(serialize_u64(v: u64));
+        always_fail!(serialize_f32(v: f32));
+        always_fail!(serialize_f64(v: f64));
+        always_fail!(serialize_char(v: char));
+        always_fail!(serialize_str(v: &str));
+        always_fail!(serialize_bytes(v: &[u8]));
+        always_fail!(serialize_none());
+        always_fail!(serialize_unit());
+        always_fail!(serialize_unit_struct(name: &'static str));
+        always_fail!(serialize_unit_variant(name: &'static str, variant_index: u32, variant: &'static str));
+
+        fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> Result<(), TestError> {
+            Err(TestError("serializer failed".to_owned()))
+        }
+
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            _value: &T,
+        ) -> Result<(), TestError> {
+            Err(TestError("serializer failed".to_owned()))
+        }
+
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _value: &T,
+        ) -> Result<(), TestError> {
+            Err(TestError("serializer failed".to_owned()))
+        }
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, TestError> {
+            Err(TestError("serializer failed".to_owned()))
+        }
+
+        fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, TestError> {
+            Err(TestError("serializer failed".to_owned()))
+        }
+
+        fn serialize_tuple_struct(
+            self,
+            _name: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleStruct, TestError> {
+            Err(TestError("serializer failed".to_owned()))
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleVariant, TestError> {
+            Err(TestError("serializer failed".to_owned()))
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, TestError> {
+            Err(TestError("serializer failed".to_owned()))
+        }
+
+        fn serialize_struct(
+            self,
+            _name: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStruct, TestError> {
+            Err(TestError("serializer failed".to_owned()))
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _variant_index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStructVariant, TestError> {
+            Err(TestError("serializer failed".to_owned()))
+        }
+    }
+
+    #[test]
+    fn transcodes_scalars() {
+        let mut tokens = Vec::new();
+        transcode(TestValue::Bool(true), TokenSerializer { tokens: &mut tokens }).unwrap();
+        assert_eq!(tokens, vec![Tok::Bool(true)]);
+    }
+
+    #[test]
+    fn transcodes_nested_seq_and_map() {
+        let input = TestValue::Map(vec![
+            ("a", TestValue::I64(1)),
+            ("b", TestValue::Seq(vec![TestValue::I64(2), TestValue::I64(3)])),
+        ]);
+        let mut tokens = Vec::new();
+        transcode(input, TokenSerializer { tokens: &mut tokens }).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Tok::MapStart,
+                Tok::Str("a".to_owned()),
+                Tok::I64(1),
+                Tok::Str("b".to_owned()),
+                Tok::SeqStart,
+                Tok::I64(2),
+                Tok::I64(3),
+                Tok::SeqEnd,
+                Tok::MapEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn transcodes_none_and_newtype() {
+        let mut tokens = Vec::new();
+        transcode(TestValue::NoneValue, TokenSerializer { tokens: &mut tokens }).unwrap();
+        assert_eq!(tokens, vec![Tok::None]);
+
+        let mut tokens = Vec::new();
+        transcode(
+            TestValue::Newtype(Box::new(TestValue::I64(7))),
+            TokenSerializer { tokens: &mut tokens },
+        ).unwrap();
+        assert_eq!(tokens, vec![Tok::I64(7)]);
+    }
+
+    #[test]
+    fn surfaces_deserializer_errors_as_de() {
+        let mut tokens = Vec::new();
+        let err = transcode(TestValue::Fail, TokenSerializer { tokens: &mut tokens }).unwrap_err();
+        match err {
+            TranscodeError::De(_) => {}
+            TranscodeError::Ser(_) => panic!("expected a De error"),
+        }
+    }
+
+    #[test]
+    fn surfaces_serializer_errors_as_ser() {
+        let err = transcode(TestValue::Bool(true), AlwaysFailSerializer).unwrap_err();
+        match err {
+            TranscodeError::Ser(_) => {}
+            TranscodeError::De(_) => panic!("expected a Ser error"),
+        }
+    }
+}